@@ -0,0 +1,116 @@
+// Web/wasm-capable backend built on macroquad. No native game controller
+// support here (macroquad doesn't expose one) - keyboard only.
+use std::path::Path;
+
+use macroquad::prelude::*;
+
+use crate::core::{Clock, Core, Input, InputSource, Renderer};
+use crate::level;
+
+struct MacroquadClock {
+    last: f64,
+}
+
+impl MacroquadClock {
+    fn new() -> MacroquadClock {
+        MacroquadClock { last: get_time() }
+    }
+}
+
+impl Clock for MacroquadClock {
+    fn tick(&mut self) -> f64 {
+        let now = get_time();
+        let dt = now - self.last;
+        self.last = now;
+        dt
+    }
+}
+
+struct MacroquadInputSource;
+
+impl InputSource for MacroquadInputSource {
+    fn poll(&mut self, input: &mut Input) -> bool {
+        input.key_escape.set(is_key_down(KeyCode::Escape));
+        input.key_enter.set(is_key_down(KeyCode::Enter));
+        input.key_up.set(is_key_down(KeyCode::Up));
+        input.key_left.set(is_key_down(KeyCode::Left));
+        input.key_down.set(is_key_down(KeyCode::Down));
+        input.key_right.set(is_key_down(KeyCode::Right));
+
+        true
+    }
+}
+
+struct MacroquadRenderer {
+    tileset: Texture2D,
+    tile_size: (u32, u32),
+    scale: f32,
+}
+
+impl Renderer for MacroquadRenderer {
+    fn clear(&mut self) {
+        clear_background(BLACK);
+    }
+
+    fn blit(&mut self, cell_x: u32, cell_y: u32, chr: u8) {
+        let src = Rect::new(
+            (chr as u32 % 16) as f32 * self.tile_size.0 as f32,
+            (chr as u32 / 16) as f32 * self.tile_size.1 as f32,
+            self.tile_size.0 as f32,
+            self.tile_size.1 as f32,
+        );
+
+        draw_texture_ex(
+            self.tileset,
+            cell_x as f32 * self.tile_size.0 as f32 * self.scale,
+            cell_y as f32 * self.tile_size.1 as f32 * self.scale,
+            WHITE,
+            DrawTextureParams {
+                source: Some(src),
+                dest_size: Some(vec2(
+                    self.tile_size.0 as f32 * self.scale,
+                    self.tile_size.1 as f32 * self.scale,
+                )),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn present(&mut self) {
+        // macroquad presents implicitly when the host awaits `next_frame`.
+    }
+}
+
+pub async fn run(level_path: String, seed: Option<u64>) {
+    let viewport_tile_count = (30, 20);
+    let tile_size = (24, 24);
+    let scale = 2.0;
+
+    let tileset = load_texture("assets/tileset_24_24.bmp").await
+        .expect("failed to load tileset");
+
+    let level_def = level::load(Path::new(&level_path))
+        .expect("failed to load level");
+    let seed = seed.unwrap_or_else(|| rand::random());
+    let world = level::build_world(level_def, seed)
+        .expect("failed to build level");
+
+    let mut core = Core::new(world, viewport_tile_count);
+    let mut clock = MacroquadClock::new();
+    let mut input_source = MacroquadInputSource;
+    let mut renderer = MacroquadRenderer { tileset, tile_size, scale };
+
+    loop {
+        let dt = clock.tick();
+
+        if !core.step(dt, &mut input_source) {
+            break;
+        }
+
+        if core.draw_due(dt) {
+            core.render(&mut renderer);
+        }
+
+        next_frame().await;
+    }
+}
@@ -0,0 +1,7 @@
+// Each backend wires a host environment's window/input/clock into
+// `core::Core` via the `Renderer`, `InputSource` and `Clock` traits.
+#[cfg(feature = "sdl2-backend")]
+pub mod sdl2_backend;
+
+#[cfg(feature = "macroquad-backend")]
+pub mod macroquad_backend;
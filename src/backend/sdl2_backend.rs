@@ -0,0 +1,291 @@
+// Desktop backend: SDL2 window/canvas, keyboard + game controller input,
+// and an `Instant`-based clock.
+use std::path::Path;
+use std::time::Instant;
+
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::keyboard::Scancode;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, Texture};
+use sdl2::video::Window;
+use sdl2::EventPump;
+
+use crate::core::{Clock, Core, Input, InputSource, Renderer};
+use crate::level;
+use crate::replay::{Recorder, Replayer};
+
+// Left stick travels -32768..32767; ignore small drift around center.
+const CONTROLLER_AXIS_DEADZONE: i16 = 8000;
+
+struct SdlClock {
+    last: Instant,
+}
+
+impl SdlClock {
+    fn new() -> SdlClock {
+        SdlClock { last: Instant::now() }
+    }
+}
+
+impl Clock for SdlClock {
+    fn tick(&mut self) -> f64 {
+        let now = Instant::now();
+        let dt = (now - self.last).as_secs_f64();
+        self.last = now;
+        dt
+    }
+}
+
+struct SdlInputSource<'a> {
+    event_pump: &'a mut EventPump,
+}
+
+impl<'a> InputSource for SdlInputSource<'a> {
+    fn poll(&mut self, input: &mut Input) -> bool {
+        let mut running = true;
+
+        for event in self.event_pump.poll_iter() {
+            match event {
+                Event::Quit {..}  => {
+                    running = false;
+                },
+                Event::KeyDown {scancode: Some(Scancode::Escape), ..} => {
+                    input.key_escape.set(true);
+                },
+                Event::KeyUp {scancode: Some(Scancode::Escape), ..} => {
+                    input.key_escape.set(false);
+                },
+                Event::KeyDown {scancode: Some(Scancode::Return), ..} => {
+                    input.key_enter.set(true);
+                },
+                Event::KeyUp {scancode: Some(Scancode::Return), ..} => {
+                    input.key_enter.set(false);
+                },
+                Event::KeyDown {scancode: Some(Scancode::Up), ..} => {
+                    input.key_up.set(true);
+                },
+                Event::KeyUp {scancode: Some(Scancode::Up), ..} => {
+                    input.key_up.set(false);
+                },
+                Event::KeyDown {scancode: Some(Scancode::Left), ..} => {
+                    input.key_left.set(true);
+                },
+                Event::KeyUp {scancode: Some(Scancode::Left), ..} => {
+                    input.key_left.set(false);
+                },
+                Event::KeyDown {scancode: Some(Scancode::Down), ..} => {
+                    input.key_down.set(true);
+                },
+                Event::KeyUp {scancode: Some(Scancode::Down), ..} => {
+                    input.key_down.set(false);
+                },
+                Event::KeyDown {scancode: Some(Scancode::Right), ..} => {
+                    input.key_right.set(true);
+                },
+                Event::KeyUp {scancode: Some(Scancode::Right), ..} => {
+                    input.key_right.set(false);
+                },
+                Event::ControllerButtonDown {button: Button::Back, ..} => {
+                    input.key_escape.set(true);
+                },
+                Event::ControllerButtonUp {button: Button::Back, ..} => {
+                    input.key_escape.set(false);
+                },
+                Event::ControllerButtonDown {button: Button::Start, ..} => {
+                    input.key_enter.set(true);
+                },
+                Event::ControllerButtonUp {button: Button::Start, ..} => {
+                    input.key_enter.set(false);
+                },
+                Event::ControllerButtonDown {button: Button::DPadUp, ..} => {
+                    input.key_up.set(true);
+                },
+                Event::ControllerButtonUp {button: Button::DPadUp, ..} => {
+                    input.key_up.set(false);
+                },
+                Event::ControllerButtonDown {button: Button::DPadLeft, ..} => {
+                    input.key_left.set(true);
+                },
+                Event::ControllerButtonUp {button: Button::DPadLeft, ..} => {
+                    input.key_left.set(false);
+                },
+                Event::ControllerButtonDown {button: Button::DPadDown, ..} => {
+                    input.key_down.set(true);
+                },
+                Event::ControllerButtonUp {button: Button::DPadDown, ..} => {
+                    input.key_down.set(false);
+                },
+                Event::ControllerButtonDown {button: Button::DPadRight, ..} => {
+                    input.key_right.set(true);
+                },
+                Event::ControllerButtonUp {button: Button::DPadRight, ..} => {
+                    input.key_right.set(false);
+                },
+                Event::ControllerAxisMotion {axis: Axis::LeftX, value, ..} => {
+                    if value > CONTROLLER_AXIS_DEADZONE {
+                        input.key_right.set(true);
+                        input.key_left.set(false);
+                    } else if value < -CONTROLLER_AXIS_DEADZONE {
+                        input.key_left.set(true);
+                        input.key_right.set(false);
+                    }
+                    // Else the stick settled back near center: leave the
+                    // latched direction alone so the snake keeps going, we
+                    // just stop producing new front-edges for it.
+                },
+                Event::ControllerAxisMotion {axis: Axis::LeftY, value, ..} => {
+                    if value > CONTROLLER_AXIS_DEADZONE {
+                        input.key_down.set(true);
+                        input.key_up.set(false);
+                    } else if value < -CONTROLLER_AXIS_DEADZONE {
+                        input.key_up.set(true);
+                        input.key_down.set(false);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        running
+    }
+}
+
+// Picks between a live SDL event pump, a recording wrapper around one, or
+// a pre-recorded replay, while presenting a single `InputSource` to `Core`.
+enum SdlInput<'a> {
+    Live(SdlInputSource<'a>),
+    Recording(Recorder<SdlInputSource<'a>>),
+    Replaying(Replayer),
+}
+
+impl<'a> InputSource for SdlInput<'a> {
+    fn poll(&mut self, input: &mut Input) -> bool {
+        match self {
+            SdlInput::Live(s) => s.poll(input),
+            SdlInput::Recording(s) => s.poll(input),
+            SdlInput::Replaying(s) => s.poll(input),
+        }
+    }
+}
+
+struct SdlRenderer<'t> {
+    canvas: Canvas<Window>,
+    texture: Texture<'t>,
+    tile_size: (u32, u32),
+    scale: u32,
+    src_rect: Rect,
+    dst_rect: Rect,
+}
+
+impl<'t> Renderer for SdlRenderer<'t> {
+    fn clear(&mut self) {
+        self.canvas.clear();
+    }
+
+    fn blit(&mut self, cell_x: u32, cell_y: u32, chr: u8) {
+        self.src_rect.set_x(((chr as usize % 16) * self.tile_size.0 as usize) as i32);
+        self.src_rect.set_y(((chr as usize / 16) * self.tile_size.1 as usize) as i32);
+
+        self.dst_rect.set_x((cell_x * self.tile_size.0 * self.scale) as i32);
+        self.dst_rect.set_y((cell_y * self.tile_size.1 * self.scale) as i32);
+
+        let _ = self.canvas.copy_ex(&self.texture, Some(self.src_rect), Some(self.dst_rect), 0.0, None, false, false);
+    }
+
+    fn present(&mut self) {
+        self.canvas.present();
+    }
+}
+
+pub fn run(
+    level_path: &str,
+    seed: Option<u64>,
+    record_path: Option<String>,
+    replay_path: Option<String>,
+) -> Result<(), String> {
+    let scale = 2;
+    // Size of the window/viewport, in tiles. The level itself can be
+    // larger; `Core`'s camera decides which part of it is visible.
+    let viewport_tile_count = (30, 20);
+    let tile_size = (24, 24);
+
+    sdl2::hint::set("SDL_VIDEO_X11_NET_WM_BYPASS_COMPOSITOR", "0");
+
+    let sdl_context = sdl2::init()?;
+    let video_subsystem = sdl_context.video()?;
+    let game_controller_subsystem = sdl_context.game_controller()?;
+
+    // Open the first attached controller, if any. Kept alive for the
+    // lifetime of `run` so SDL keeps delivering its events; the game plays
+    // fine with only a keyboard if none is found.
+    let _controller: Option<GameController> = (0..game_controller_subsystem.num_joysticks()?)
+        .find(|&id| game_controller_subsystem.is_game_controller(id))
+        .and_then(|id| game_controller_subsystem.open(id).ok());
+
+    let window = video_subsystem.window("SDL2",
+                    scale * viewport_tile_count.0 * tile_size.0,
+                    scale * viewport_tile_count.1 * tile_size.1)
+        .position_centered().build().map_err(|e| e.to_string())?;
+
+    let mut canvas = window.into_canvas()
+        .accelerated().build().map_err(|e| e.to_string())?;
+    canvas.set_draw_color(sdl2::pixels::Color::RGBA(0,0,0,255));
+
+    let texture_creator = canvas.texture_creator();
+    let tileset_surface = sdl2::surface::Surface::load_bmp(Path::new("assets/tileset_24_24.bmp"))?;
+    let texture = texture_creator.create_texture_from_surface(&tileset_surface)
+        .map_err(|e| e.to_string())?;
+
+    let mut renderer = SdlRenderer {
+        canvas,
+        texture,
+        tile_size,
+        scale,
+        src_rect: Rect::new(16, 0, tile_size.0, tile_size.1),
+        dst_rect: Rect::new(0, 0, tile_size.0 * scale, tile_size.1 * scale),
+    };
+
+    let mut event_pump = sdl_context.event_pump()?;
+
+    // A replay carries its own seed, since the whole point is reproducing
+    // the exact run it was recorded from; otherwise fall back to an
+    // explicit `--seed` or a random one.
+    let replayer = replay_path.map(|path| Replayer::load(Path::new(&path))).transpose()?;
+    let seed = match &replayer {
+        Some((recorded_seed, _)) => *recorded_seed,
+        None => seed.unwrap_or_else(rand::random),
+    };
+
+    let level_def = level::load(Path::new(level_path))?;
+    let world = level::build_world(level_def, seed)?;
+
+    let mut core = Core::new(world, viewport_tile_count);
+    let mut clock = SdlClock::new();
+
+    let mut input_source = match replayer {
+        Some((_, replayer)) => SdlInput::Replaying(replayer),
+        None => {
+            let live = SdlInputSource { event_pump: &mut event_pump };
+
+            match record_path {
+                Some(path) => SdlInput::Recording(Recorder::new(live, path, seed)),
+                None => SdlInput::Live(live),
+            }
+        },
+    };
+
+    loop {
+        let dt = clock.tick();
+
+        if !core.step(dt, &mut input_source) {
+            break;
+        }
+
+        if core.draw_due(dt) {
+            core.render(&mut renderer);
+        }
+    }
+
+    Ok(())
+}
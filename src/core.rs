@@ -0,0 +1,748 @@
+// Backend-agnostic game logic: world state, simulation and the fixed-step
+// loop that drives it. Rendering and input are behind the `Renderer` and
+// `InputSource` traits so `backend::sdl2_backend` and
+// `backend::macroquad_backend` can each plug in their own.
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl From<u32> for Direction {
+    fn from(x: u32) -> Direction {
+        match x % 4 {
+            0 => Direction::Up,
+            1 => Direction::Right,
+            2 => Direction::Down,
+            3 => Direction::Left,
+            _ => panic!(),
+        }
+    }
+}
+
+impl From<Direction> for u32 {
+    fn from(x: Direction) -> u32 {
+        match x {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+}
+
+impl Direction {
+    fn cw(&self) -> Direction {
+        let x: u32 = (*self).into();
+        x.overflowing_add(1).0.into()
+    }
+
+    fn ccw(&self) -> Direction {
+        let x: u32 = (*self).into();
+        x.overflowing_sub(1).0.into()
+    }
+
+    fn is_opposite(&self, direction: Direction) -> bool {
+        let d1: u32 = (*self).into();
+        let d2: u32 = direction.into();
+
+        let delta_dir: Direction = d1.overflowing_sub(d2).0.into();
+
+        delta_dir == Direction::Down
+    }
+}
+
+pub(crate) struct Latch {
+    prev: bool,
+    curr: bool,
+}
+
+impl Latch {
+    fn new() -> Latch {
+        Latch {
+            prev: false,
+            curr: false,
+        }
+    }
+
+    pub(crate) fn set(&mut self, value: bool) {
+        self.curr = value;
+    }
+
+    pub(crate) fn front_edge(&self) -> bool {
+        self.curr && ! self.prev
+    }
+
+    fn tick(&mut self) {
+        self.prev = self.curr;
+    }
+}
+
+struct ScreenBuffer {
+    chars: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl ScreenBuffer {
+    fn new(width: u32, height: u32) -> ScreenBuffer {
+        ScreenBuffer {
+            chars: vec![0; width as usize * height as usize],
+            width,
+            height,
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width);
+        assert!(y < self.height);
+
+        (y * self.width + x) as usize
+    }
+
+    fn clear(&mut self) {
+        for c in self.chars.iter_mut() {
+            *c = 0;
+        }
+    }
+}
+
+fn print(buf: &mut ScreenBuffer, x: u32, y: u32, s: &[u8]) {
+    let index = buf.index(x, y);
+    let len = s.len();
+
+    buf.chars[index..(index + len)].copy_from_slice(s);
+}
+
+#[derive(Eq, PartialEq)]
+enum GameState {
+    Menu,
+    Play,
+    Pause,
+    GameOver,
+    Quit,
+}
+
+const SUBPIXEL: i32 = 0x200;
+const CAMERA_EASING: i32 = 8;
+
+struct Camera {
+    x: i32,
+    y: i32,
+}
+
+impl Camera {
+    fn new() -> Camera {
+        Camera { x: 0, y: 0 }
+    }
+
+    fn update(world: &mut World, viewport_tile_count: (u32, u32)) {
+        let level_width_px = world.level_bounds.width as i32 * SUBPIXEL;
+        let level_height_px = world.level_bounds.height as i32 * SUBPIXEL;
+        let viewport_width_px = viewport_tile_count.0 as i32 * SUBPIXEL;
+        let viewport_height_px = viewport_tile_count.1 as i32 * SUBPIXEL;
+
+        let (head_x, head_y) = *world.snake.body.front().unwrap();
+        let head_px = head_x as i32 * SUBPIXEL;
+        let head_py = head_y as i32 * SUBPIXEL;
+
+        let target_x = if level_width_px <= viewport_width_px {
+            -(viewport_width_px - level_width_px) / 2
+        } else {
+            (head_px - viewport_width_px / 2).clamp(0, level_width_px - viewport_width_px)
+        };
+
+        let target_y = if level_height_px <= viewport_height_px {
+            -(viewport_height_px - level_height_px) / 2
+        } else {
+            (head_py - viewport_height_px / 2).clamp(0, level_height_px - viewport_height_px)
+        };
+
+        world.camera.x += (target_x - world.camera.x) / CAMERA_EASING;
+        world.camera.y += (target_y - world.camera.y) / CAMERA_EASING;
+    }
+
+    // Top-left of the viewport, in whole tiles.
+    fn tile_offset(&self) -> (i32, i32) {
+        (self.x.div_euclid(SUBPIXEL), self.y.div_euclid(SUBPIXEL))
+    }
+}
+
+pub(crate) struct LevelBounds {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl LevelBounds {
+    pub(crate) fn new(x: u32, y: u32, width: u32, height: u32) -> LevelBounds {
+        LevelBounds {
+            x, y, width, height,
+        }
+    }
+
+    fn draw(&self, screen_buffer: &mut ScreenBuffer) {
+        let wall_chr = [0xb1u8];
+
+        for x in self.x..(self.x + self.width) {
+            print(screen_buffer, x, self.y, &wall_chr);
+        }
+        for y in self.y..(self.y + self.height) {
+            print(screen_buffer, self.x, y, &wall_chr);
+        }
+        for x in self.x..(self.x + self.width) {
+            print(screen_buffer, x, self.y + self.height - 1, &wall_chr);
+        }
+        for y in self.y..(self.y + self.height) {
+            print(screen_buffer, self.x + self.width - 1, y, &wall_chr);
+        }
+    }
+
+    fn is_inside(&self, x: u32, y: u32) -> bool {
+        x > self.x && x < (self.x + self.width - 1) && y > self.y && y < (self.y + self.height - 1)
+    }
+}
+
+// Interior obstacles, stored as a flat tile grid the size of the level.
+pub(crate) struct Walls {
+    tiles: Vec<bool>,
+    width: u32,
+    height: u32,
+}
+
+impl Walls {
+    fn new(width: u32, height: u32) -> Walls {
+        Walls {
+            tiles: vec![false; width as usize * height as usize],
+            width,
+            height,
+        }
+    }
+
+    pub(crate) fn set(&mut self, x: u32, y: u32) {
+        let index = (y * self.width + x) as usize;
+        self.tiles[index] = true;
+    }
+
+    fn is_wall(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+
+        self.tiles[(y * self.width + x) as usize]
+    }
+
+    fn draw(&self, screen_buffer: &mut ScreenBuffer) {
+        let wall_chr = [0xb2u8];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.is_wall(x, y) {
+                    print(screen_buffer, x, y, &wall_chr);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) struct Snake {
+    body: VecDeque<(u32, u32)>,
+    prev_direction: Direction,
+    direction: Direction,
+    period: u32,
+    tick: u32,
+    score: u32,
+    dead: bool,
+}
+
+#[derive(Eq, PartialEq)]
+enum SnakeCollision {
+    Head,
+    Tail,
+}
+
+impl Snake {
+    // Levels supply the starting body, heading and speed; see `level`.
+    pub(crate) fn with_body(body: VecDeque<(u32, u32)>, direction: Direction, period: u32) -> Snake {
+        Snake {
+            body,
+            prev_direction: direction,
+            direction,
+            period,
+            tick: 0,
+            score: 0,
+            dead: false,
+        }
+    }
+
+    fn is_collision(&self, x: u32, y: u32) -> Option<SnakeCollision> {
+        for (i, p) in self.body.iter().copied().enumerate() {
+            if i == 0 {
+                if p == (x, y) {
+                    return Some(SnakeCollision::Head);
+                }
+            }
+            if p == (x, y) {
+                return Some(SnakeCollision::Tail);
+            }
+        }
+
+        None
+    }
+
+    fn grow(&mut self, n: u32) {
+        let back = self.body.back().unwrap().clone();
+
+        for _ in 0..n {
+            self.body.push_back(back);
+        }
+    }
+
+    // The one path every food type's reward runs through, so apples, bonus
+    // food and anything added later all affect the snake the same way.
+    fn apply_growth(&mut self, event: GrowthEvent) {
+        self.grow(event.growth);
+        self.score += event.score;
+    }
+
+    fn move_up(&mut self) {
+        if !self.prev_direction.is_opposite(Direction::Up) {
+            self.direction = Direction::Up;
+        }
+    }
+
+    fn move_right(&mut self) {
+        if !self.prev_direction.is_opposite(Direction::Right) {
+            self.direction = Direction::Right;
+        }
+    }
+
+    fn move_down(&mut self) {
+        if !self.prev_direction.is_opposite(Direction::Down) {
+            self.direction = Direction::Down;
+        }
+    }
+
+    fn move_left(&mut self) {
+        if !self.prev_direction.is_opposite(Direction::Left) {
+            self.direction = Direction::Left;
+        }
+    }
+
+    fn update(world: &mut World) {
+        if world.snake.dead {
+            return;
+        }
+
+        world.snake.tick = world.snake.tick + 1;
+        if world.snake.tick > world.snake.period {
+            world.snake.tick = 0;
+
+            let (x, y) = world.snake.body.front().unwrap().clone();
+
+            let (new_x, new_y) = match world.snake.direction {
+                Direction::Up => (x, y - 1),
+                Direction::Right => (x + 1, y),
+                Direction::Down => (x, y + 1),
+                Direction::Left => (x - 1, y),
+            };
+
+            let object_id = world.check_collision(ObjectId::SnakeHead, new_x, new_y);
+
+            if object_id == Some(ObjectId::LevelBound) {
+                world.snake.dead = true;
+            } else if object_id == Some(ObjectId::SnakeTail) {
+                world.snake.dead = true;
+            } else if object_id == Some(ObjectId::Wall) {
+                world.snake.dead = true;
+            } else if object_id == Some(ObjectId::Apple) {
+                world.snake.apply_growth(GrowthEvent { growth: 1, score: 1 });
+            } else if object_id == Some(ObjectId::Bonus) {
+                world.snake.apply_growth(GrowthEvent { growth: BONUS_GROWTH, score: BONUS_SCORE });
+                world.bonus.pos = None;
+            }
+
+            if world.snake.dead {
+                return;
+            }
+
+            world.snake.body.push_front((new_x, new_y));
+            world.snake.body.pop_back();
+
+            world.snake.prev_direction = world.snake.direction;
+        }
+    }
+
+    fn draw(&self, screen_buffer: &mut ScreenBuffer) {
+        for (i, (x, y)) in self.body.iter().enumerate() {
+            if i != 0 {
+                print(screen_buffer, *x, *y, b"#");
+            }
+        }
+        let (x, y) = self.body.front().unwrap();
+        print(screen_buffer, *x, *y, b"O");
+    }
+}
+
+// Front-edge direction latches, shared between every input source a backend
+// feeds: a keyboard, a controller's d-pad, or a recorded replay.
+pub(crate) struct Input {
+    pub(crate) key_escape: Latch,
+    pub(crate) key_enter: Latch,
+    pub(crate) key_up: Latch,
+    pub(crate) key_left: Latch,
+    pub(crate) key_down: Latch,
+    pub(crate) key_right: Latch,
+}
+
+impl Input {
+    fn new() -> Input {
+        Input {
+            key_escape: Latch::new(),
+            key_enter: Latch::new(),
+            key_up: Latch::new(),
+            key_left: Latch::new(),
+            key_down: Latch::new(),
+            key_right: Latch::new(),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.key_escape.tick();
+        self.key_enter.tick();
+        self.key_up.tick();
+        self.key_left.tick();
+        self.key_down.tick();
+        self.key_right.tick();
+    }
+}
+
+// What eating a piece of food does to the snake.
+struct GrowthEvent {
+    growth: u32,
+    score: u32,
+}
+
+pub(crate) struct Apple {
+    pos: Option<(u32, u32)>,
+}
+
+impl Apple {
+    pub(crate) fn new() -> Apple {
+        Apple {
+            pos: None,
+        }
+    }
+
+    fn update(world: &mut World) {
+        for i in 0..world.apples.len() {
+            let needs_new_pos = match world.apples[i].pos {
+                None => true,
+                Some((x, y)) => world.check_collision(ObjectId::Apple, x, y) == Some(ObjectId::SnakeHead),
+            };
+
+            if needs_new_pos {
+                let mut occupied: Vec<(u32, u32)> = world.apples.iter().filter_map(|a| a.pos).collect();
+                occupied.extend(world.bonus.pos);
+                let pos = world.gen_food_pos(&occupied);
+                world.apples[i].pos = Some(pos);
+            }
+        }
+    }
+
+    fn draw(&self, screen_buffer: &mut ScreenBuffer) {
+        if let Some((x, y)) = self.pos {
+            print(screen_buffer, x, y, b"$");
+        }
+    }
+}
+
+const BONUS_SPAWN_PERIOD: u32 = 600;
+const BONUS_LIFETIME: u32 = 200;
+const BONUS_GROWTH: u32 = 3;
+const BONUS_SCORE: u32 = 5;
+
+// A food item that appears on a timer, is worth more than a regular apple,
+// and vanishes on its own if the snake doesn't reach it in time.
+struct Bonus {
+    pos: Option<(u32, u32)>,
+    lifetime: u32,
+    spawn_timer: u32,
+}
+
+impl Bonus {
+    fn new() -> Bonus {
+        Bonus {
+            pos: None,
+            lifetime: 0,
+            spawn_timer: 0,
+        }
+    }
+
+    fn update(world: &mut World) {
+        if world.bonus.pos.is_some() {
+            world.bonus.lifetime -= 1;
+            if world.bonus.lifetime == 0 {
+                world.bonus.pos = None;
+            }
+            return;
+        }
+
+        world.bonus.spawn_timer += 1;
+        if world.bonus.spawn_timer >= BONUS_SPAWN_PERIOD {
+            world.bonus.spawn_timer = 0;
+
+            let occupied: Vec<(u32, u32)> = world.apples.iter().filter_map(|a| a.pos).collect();
+            let pos = world.gen_food_pos(&occupied);
+            world.bonus.pos = Some(pos);
+            world.bonus.lifetime = BONUS_LIFETIME;
+        }
+    }
+
+    fn draw(&self, screen_buffer: &mut ScreenBuffer) {
+        if let Some((x, y)) = self.pos {
+            print(screen_buffer, x, y, b"%");
+        }
+    }
+}
+
+#[derive(Eq, PartialEq)]
+enum ObjectId {
+    SnakeHead,
+    SnakeTail,
+    LevelBound,
+    Wall,
+    Apple,
+    Bonus,
+}
+
+pub(crate) struct World {
+    snake: Snake,
+    level_bounds: LevelBounds,
+    pub(crate) walls: Walls,
+    pub(crate) apples: Vec<Apple>,
+    bonus: Bonus,
+    camera: Camera,
+    rng: StdRng,
+}
+
+impl World {
+    pub(crate) fn new(snake: Snake, level_bounds: LevelBounds, seed: u64) -> World {
+        let walls = Walls::new(level_bounds.width, level_bounds.height);
+        let apples = vec![Apple::new()];
+        let bonus = Bonus::new();
+        let camera = Camera::new();
+        let rng = StdRng::seed_from_u64(seed);
+
+        World {
+            snake,
+            level_bounds,
+            walls,
+            apples,
+            bonus,
+            camera,
+            rng,
+        }
+    }
+
+    // Picks a free tile for a new piece of food: not on the snake, not a
+    // wall, and not already occupied by another food item. The RNG lives on
+    // `World` (rather than `rand::thread_rng()`) so a whole game's outcome
+    // is reproducible from its seed alone.
+    fn gen_food_pos(&mut self, occupied: &[(u32, u32)]) -> (u32, u32) {
+        loop {
+            let x = self.rng.gen_range(self.level_bounds.x + 1, self.level_bounds.x + self.level_bounds.width - 1);
+            let y = self.rng.gen_range(self.level_bounds.y + 1, self.level_bounds.y + self.level_bounds.height - 1);
+
+            if self.snake.is_collision(x, y) == None && !self.walls.is_wall(x, y) && !occupied.contains(&(x, y)) {
+                return (x, y);
+            }
+        }
+    }
+
+    fn check_collision(&self, id: ObjectId, x: u32, y: u32) -> Option<ObjectId> {
+        // We assume that object does not collide with itself.
+
+        if id != ObjectId::LevelBound && !self.level_bounds.is_inside(x, y) {
+            return Some(ObjectId::LevelBound);
+        }
+
+        if id != ObjectId::Wall && self.walls.is_wall(x, y) {
+            return Some(ObjectId::Wall);
+        }
+
+        if let Some(snake_collision) = self.snake.is_collision(x, y) {
+            if id != ObjectId::SnakeHead && snake_collision == SnakeCollision::Head {
+                return Some(ObjectId::SnakeHead);
+            } else if id != ObjectId::SnakeTail && snake_collision == SnakeCollision::Tail {
+                return Some(ObjectId::SnakeTail);
+            }
+        }
+
+        if id != ObjectId::Apple && self.apples.iter().any(|a| a.pos == Some((x, y))) {
+            return Some(ObjectId::Apple);
+        }
+
+        if id != ObjectId::Bonus && self.bonus.pos == Some((x, y)) {
+            return Some(ObjectId::Bonus);
+        }
+
+        None
+    }
+}
+
+// A tile cell, addressed by column/row within the viewport (not the level).
+pub(crate) trait Renderer {
+    fn clear(&mut self);
+    fn blit(&mut self, cell_x: u32, cell_y: u32, chr: u8);
+    fn present(&mut self);
+}
+
+pub(crate) trait InputSource {
+    // Polls pending events into `input`'s latches. Returns `false` once the
+    // host wants the game to quit (e.g. the window was closed).
+    fn poll(&mut self, input: &mut Input) -> bool;
+}
+
+pub(crate) trait Clock {
+    // Seconds elapsed since the previous call (on the first call, since the
+    // clock was created).
+    fn tick(&mut self) -> f64;
+}
+
+const UPDATE_PERIOD: f64 = 1.0 / 120.0;
+const DRAW_PERIOD: f64 = 1.0 / 60.0;
+
+// Backend-agnostic game loop: fixed 120 Hz updates, 60 Hz draws, both driven
+// by a `dt` the host hands in from its own frame callback (an `Instant`-based
+// loop for SDL2, a `requestAnimationFrame` tick under wasm/macroquad).
+pub(crate) struct Core {
+    world: World,
+    screen_buffer: ScreenBuffer,
+    input: Input,
+    state: GameState,
+    viewport_tile_count: (u32, u32),
+    update_accum: f64,
+    draw_accum: f64,
+}
+
+impl Core {
+    pub(crate) fn new(world: World, viewport_tile_count: (u32, u32)) -> Core {
+        let screen_buffer = ScreenBuffer::new(world.level_bounds.width, world.level_bounds.height);
+
+        Core {
+            world,
+            screen_buffer,
+            input: Input::new(),
+            state: GameState::Play,
+            viewport_tile_count,
+            update_accum: 0.0,
+            draw_accum: 0.0,
+        }
+    }
+
+    // Runs as many fixed update ticks as `dt` seconds allow. Returns `false`
+    // once the host should stop calling `step`/`render` and tear down.
+    pub(crate) fn step(&mut self, dt: f64, input_source: &mut dyn InputSource) -> bool {
+        self.update_accum += dt;
+
+        while self.update_accum >= UPDATE_PERIOD {
+            self.update_accum -= UPDATE_PERIOD;
+
+            if !input_source.poll(&mut self.input) {
+                self.state = GameState::Quit;
+            }
+
+            if self.state == GameState::Play {
+                if self.input.key_up.front_edge() {
+                    self.world.snake.move_up();
+                }
+                if self.input.key_right.front_edge() {
+                    self.world.snake.move_right();
+                }
+                if self.input.key_down.front_edge() {
+                    self.world.snake.move_down();
+                }
+                if self.input.key_left.front_edge() {
+                    self.world.snake.move_left();
+                }
+
+                Snake::update(&mut self.world);
+                if self.world.snake.dead {
+                    self.state = GameState::GameOver;
+                } else {
+                    Apple::update(&mut self.world);
+                    Bonus::update(&mut self.world);
+                    Camera::update(&mut self.world, self.viewport_tile_count);
+                }
+            } else if self.state == GameState::GameOver {
+                if self.input.key_escape.front_edge() {
+                    self.state = GameState::Quit;
+                }
+            }
+
+            self.input.tick();
+        }
+
+        self.state != GameState::Quit
+    }
+
+    // Returns `true` at most once per 1/60s of accumulated `dt`; `render`
+    // is only meant to be called when this does.
+    pub(crate) fn draw_due(&mut self, dt: f64) -> bool {
+        self.draw_accum += dt;
+
+        if self.draw_accum >= DRAW_PERIOD {
+            self.draw_accum -= DRAW_PERIOD;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn render(&mut self, renderer: &mut dyn Renderer) {
+        self.screen_buffer.clear();
+
+        if self.state == GameState::Play {
+            self.world.level_bounds.draw(&mut self.screen_buffer);
+            self.world.walls.draw(&mut self.screen_buffer);
+            for apple in &self.world.apples {
+                apple.draw(&mut self.screen_buffer);
+            }
+            self.world.bonus.draw(&mut self.screen_buffer);
+            self.world.snake.draw(&mut self.screen_buffer);
+        }
+        if self.state == GameState::GameOver {
+            print(&mut self.screen_buffer, 0, 0, b"Game over!");
+        }
+
+        renderer.clear();
+
+        let (cam_x, cam_y) = self.world.camera.tile_offset();
+        for y in 0..self.viewport_tile_count.1 {
+            for x in 0..self.viewport_tile_count.0 {
+                let level_x = cam_x + x as i32;
+                let level_y = cam_y + y as i32;
+
+                if level_x < 0 || level_y < 0
+                    || level_x as u32 >= self.screen_buffer.width
+                    || level_y as u32 >= self.screen_buffer.height {
+                    continue;
+                }
+
+                let chr = self.screen_buffer.chars[self.screen_buffer.index(level_x as u32, level_y as u32)];
+                renderer.blit(x, y, chr);
+            }
+        }
+
+        renderer.present();
+    }
+}
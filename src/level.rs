@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::core::{Apple, Direction, LevelBounds, Snake, World};
+
+#[derive(Deserialize)]
+pub struct LevelDef {
+    bounds: BoundsDef,
+    #[serde(default)]
+    walls: Vec<(u32, u32)>,
+    snake: SnakeDef,
+    period: u32,
+    #[serde(default = "default_apple_count")]
+    apple_count: u32,
+}
+
+#[derive(Deserialize)]
+struct BoundsDef {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Deserialize)]
+struct SnakeDef {
+    body: Vec<(u32, u32)>,
+    direction: DirectionDef,
+}
+
+#[derive(Deserialize)]
+enum DirectionDef {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl From<DirectionDef> for Direction {
+    fn from(d: DirectionDef) -> Direction {
+        match d {
+            DirectionDef::Up => Direction::Up,
+            DirectionDef::Right => Direction::Right,
+            DirectionDef::Down => Direction::Down,
+            DirectionDef::Left => Direction::Left,
+        }
+    }
+}
+
+fn default_apple_count() -> u32 {
+    1
+}
+
+pub fn load(path: &Path) -> Result<LevelDef, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    json5::from_str(&text).map_err(|e| e.to_string())
+}
+
+pub fn build_world(def: LevelDef, seed: u64) -> Result<World, String> {
+    let level_bounds = LevelBounds::new(def.bounds.x, def.bounds.y, def.bounds.width, def.bounds.height);
+
+    let body: VecDeque<(u32, u32)> = def.snake.body.into_iter().collect();
+    let direction = Direction::from(def.snake.direction);
+    let snake = Snake::with_body(body, direction, def.period);
+
+    let mut world = World::new(snake, level_bounds, seed);
+
+    for (x, y) in def.walls {
+        // `Walls` is always a `bounds.width x bounds.height` grid anchored
+        // at the origin (unlike `LevelBounds`, it isn't offset by
+        // `bounds.x/y`), so that's the range a wall coordinate has to fall
+        // inside to be addressable.
+        if x >= def.bounds.width || y >= def.bounds.height {
+            return Err(format!(
+                "wall ({}, {}) is outside the level bounds ({}x{})",
+                x, y, def.bounds.width, def.bounds.height
+            ));
+        }
+
+        world.walls.set(x, y);
+    }
+
+    world.apples = (0..def.apple_count).map(|_| Apple::new()).collect();
+
+    Ok(world)
+}
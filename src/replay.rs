@@ -0,0 +1,151 @@
+// Records and replays the input stream that drives `core::World`. Since the
+// game is a pure function of (seed, input sequence), replaying a recording
+// against the same seed reproduces the exact same apple positions, deaths
+// and score - useful for bug reports and as a cheap regression test.
+use std::fs;
+use std::path::Path;
+
+use crate::core::{Input, InputSource};
+
+// The four direction latches' front-edge state for a single tick, recorded
+// independently rather than collapsed to one `Direction`: `Core::step`
+// applies them as four separate `if`s, so more than one can front-edge on
+// the same tick (e.g. a diagonal controller stick tap), and a replay has to
+// be able to reproduce that exactly.
+#[derive(Clone, Copy, Default)]
+struct TickInput {
+    up: bool,
+    right: bool,
+    down: bool,
+    left: bool,
+}
+
+pub struct Recorder<I: InputSource> {
+    inner: I,
+    path: String,
+    seed: u64,
+    ticks: Vec<TickInput>,
+}
+
+impl<I: InputSource> Recorder<I> {
+    pub fn new(inner: I, path: String, seed: u64) -> Recorder<I> {
+        Recorder {
+            inner,
+            path,
+            seed,
+            ticks: Vec::new(),
+        }
+    }
+
+    fn save(&self) {
+        let mut text = format!("{}\n", self.seed);
+
+        for tick in &self.ticks {
+            let mut line = String::new();
+
+            if tick.up {
+                line.push('U');
+            }
+            if tick.right {
+                line.push('R');
+            }
+            if tick.down {
+                line.push('D');
+            }
+            if tick.left {
+                line.push('L');
+            }
+            if line.is_empty() {
+                line.push('-');
+            }
+
+            text.push_str(&line);
+            text.push('\n');
+        }
+
+        if let Err(e) = fs::write(&self.path, text) {
+            eprintln!("failed to write recording to {}: {}", self.path, e);
+        }
+    }
+}
+
+impl<I: InputSource> InputSource for Recorder<I> {
+    fn poll(&mut self, input: &mut Input) -> bool {
+        let running = self.inner.poll(input);
+
+        self.ticks.push(TickInput {
+            up: input.key_up.front_edge(),
+            right: input.key_right.front_edge(),
+            down: input.key_down.front_edge(),
+            left: input.key_left.front_edge(),
+        });
+
+        running
+    }
+}
+
+impl<I: InputSource> Drop for Recorder<I> {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
+
+pub struct Replayer {
+    ticks: Vec<TickInput>,
+    cursor: usize,
+}
+
+impl Replayer {
+    pub fn load(path: &Path) -> Result<(u64, Replayer), String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut lines = text.lines();
+
+        let seed: u64 = lines
+            .next()
+            .ok_or_else(|| "recording is missing its seed line".to_string())?
+            .parse()
+            .map_err(|e: std::num::ParseIntError| e.to_string())?;
+
+        let ticks = lines
+            .map(|line| {
+                if line == "-" {
+                    return Ok(TickInput::default());
+                }
+
+                let mut tick = TickInput::default();
+
+                for c in line.chars() {
+                    match c {
+                        'U' => tick.up = true,
+                        'R' => tick.right = true,
+                        'D' => tick.down = true,
+                        'L' => tick.left = true,
+                        other => return Err(format!("unrecognized recording character: {:?}", other)),
+                    }
+                }
+
+                Ok(tick)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok((seed, Replayer { ticks, cursor: 0 }))
+    }
+}
+
+impl InputSource for Replayer {
+    fn poll(&mut self, input: &mut Input) -> bool {
+        if self.cursor >= self.ticks.len() {
+            return false;
+        }
+
+        let tick = self.ticks[self.cursor];
+        self.cursor += 1;
+
+        input.key_up.set(tick.up);
+        input.key_right.set(tick.right);
+        input.key_down.set(tick.down);
+        input.key_left.set(tick.left);
+
+        true
+    }
+}